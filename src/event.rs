@@ -20,51 +20,95 @@ pub struct Event {
 }
 
 // OS specific
-// TODO: Events can have more than one filter flag
 impl Event {
   #[doc(hidden)]
   pub fn new(ev: kevent, watcher: &Watcher) -> Event {
+    // On platforms without `EVFILT_USER`, the wakeup notifier falls back to
+    // a self-pipe delivered as an ordinary `EVFILT_READ` event; recognize its
+    // reserved ident before it reaches the generic fd-lookup path below.
+    // The filter is checked as well: on the self-pipe fallback the ident is
+    // an ordinary fd number, which a timer, signal or pid ident can share.
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    let wakeup_filter = EventFilter::EVFILT_USER;
+    #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+    let wakeup_filter = EventFilter::EVFILT_READ;
+
+    if watcher.wakeup_ident == Some(ev.ident) && ev.filter == wakeup_filter {
+      // On the self-pipe fallback, `ev.ident` is the pipe's read fd; drain
+      // it so `notify()`'s writes don't accumulate and eventually fill the
+      // pipe buffer.
+      #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+      crate::notifier::drain_self_pipe(ev.ident as RawFd);
+
+      return Event {
+        ident: Ident::Wakeup,
+        data: EventData::Wakeup,
+      };
+    }
+
     let data = match ev.filter {
       EventFilter::EVFILT_READ => EventData::ReadReady(ev.data as usize),
       EventFilter::EVFILT_WRITE => EventData::WriteReady(ev.data as usize),
       EventFilter::EVFILT_SIGNAL => EventData::Signal(ev.data as usize),
       EventFilter::EVFILT_TIMER => EventData::Timer(ev.data as usize),
+      // `kqueue-sys`'s `EventFilter` doesn't define `EVFILT_USER` on every
+      // platform (e.g. OpenBSD); platforms without a working
+      // `EVFILT_USER`/`NOTE_TRIGGER` pair take the self-pipe branch above
+      // instead, so this arm is never reached there.
+      #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+      EventFilter::EVFILT_USER => EventData::Wakeup,
       EventFilter::EVFILT_PROC => {
-        let inner = if ev.fflags.contains(FilterFlag::NOTE_EXIT) {
-          Proc::Exit(ev.data as usize)
-        } else if ev.fflags.contains(FilterFlag::NOTE_FORK) {
-          Proc::Fork
-        } else if ev.fflags.contains(FilterFlag::NOTE_EXEC) {
-          Proc::Exec
-        } else if ev.fflags.contains(FilterFlag::NOTE_TRACK) {
-          Proc::Track(ev.data as libc::pid_t)
-        } else if ev.fflags.contains(FilterFlag::NOTE_CHILD) {
-          Proc::Child(ev.data as libc::pid_t)
-        } else {
-          panic!("proc filterflag not supported: {0:?}", ev.fflags)
-        };
+        let mut inner = Vec::new();
+
+        if ev.fflags.contains(FilterFlag::NOTE_EXIT) {
+          inner.push(Proc::Exit(ev.data as usize));
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_FORK) {
+          inner.push(Proc::Fork);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_EXEC) {
+          inner.push(Proc::Exec);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_TRACK) {
+          inner.push(Proc::Track(ev.data as libc::pid_t));
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_CHILD) {
+          inner.push(Proc::Child(ev.data as libc::pid_t));
+        }
+        // Any remaining, unrecognized fflags are silently skipped rather
+        // than aborting the whole event.
 
         EventData::Proc(inner)
       },
       EventFilter::EVFILT_VNODE => {
-        let inner = if ev.fflags.contains(FilterFlag::NOTE_DELETE) {
-          Vnode::Delete
-        } else if ev.fflags.contains(FilterFlag::NOTE_WRITE) {
-          Vnode::Write
-        } else if ev.fflags.contains(FilterFlag::NOTE_EXTEND) {
-          Vnode::Extend
-        } else if ev.fflags.contains(FilterFlag::NOTE_ATTRIB) {
-          Vnode::Attrib
-        } else if ev.fflags.contains(FilterFlag::NOTE_LINK) {
-          Vnode::Link
-        } else if ev.fflags.contains(FilterFlag::NOTE_RENAME) {
-          Vnode::Rename
-        } else if ev.fflags.contains(FilterFlag::NOTE_REVOKE) {
-          Vnode::Revoke
-        } else {
-          // This handles any filter flags that are OS-specific
-          vnode::handle_vnode_extras(ev.fflags)
-        };
+        let mut inner = Vec::new();
+
+        if ev.fflags.contains(FilterFlag::NOTE_DELETE) {
+          inner.push(Vnode::Delete);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_WRITE) {
+          inner.push(Vnode::Write);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_EXTEND) {
+          inner.push(Vnode::Extend);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_ATTRIB) {
+          inner.push(Vnode::Attrib);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_LINK) {
+          inner.push(Vnode::Link);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_RENAME) {
+          inner.push(Vnode::Rename);
+        }
+        if ev.fflags.contains(FilterFlag::NOTE_REVOKE) {
+          inner.push(Vnode::Revoke);
+        }
+        // This handles any remaining filter flags that are OS-specific; an
+        // unrecognized fflag set is skipped rather than aborting the event.
+        if let Some(extra) = vnode::handle_vnode_extras(ev.fflags) {
+          inner.push(extra);
+        }
 
         EventData::Vnode(inner)
       },
@@ -86,6 +130,13 @@ impl Event {
 
   #[doc(hidden)]
   pub fn from_error(ev: kevent, watcher: &Watcher) -> Event {
+    if watcher.wakeup_ident == Some(ev.ident) {
+      return Event {
+        ident: Ident::Wakeup,
+        data: EventData::Error(io::Error::last_os_error()),
+      };
+    }
+
     let ident = match ev.filter {
       EventFilter::EVFILT_READ => find_file_ident(watcher, ev.ident as RawFd).unwrap(),
       EventFilter::EVFILT_WRITE => find_file_ident(watcher, ev.ident as RawFd).unwrap(),
@@ -1,4 +1,8 @@
-use std::{fs::File, os::fd::RawFd, time::Duration};
+use std::{
+  fs::File,
+  os::fd::{AsRawFd, OwnedFd, RawFd},
+  time::Duration,
+};
 
 use libc::pid_t;
 
@@ -6,10 +10,21 @@ use libc::pid_t;
 #[derive(Debug)]
 pub enum Ident {
   Filename(File, RawFd, String),
+
+  /// A borrowed fd, supplied via `Watcher::add_fd`. The `Watcher` does not
+  /// own it and will never close it.
   Fd(RawFd),
+
+  /// An owned fd, supplied via `Watcher::add_owned_fd`. Closed exactly once,
+  /// when this `Ident` is removed from the `Watcher` or dropped.
+  OwnedFd(OwnedFd),
+
   Pid(pid_t),
   Signal(i32),
   Timer(usize, Duration),
+
+  /// The `Watcher`'s own internal wakeup event, fired by a `Notifier`.
+  Wakeup,
 }
 
 // We don't have enough information to turn a `usize` into
@@ -20,9 +35,11 @@ impl Into<usize> for Ident {
     match self {
       Ident::Filename(_, fd, _) => fd as usize,
       Ident::Fd(fd) => fd as usize,
+      Ident::OwnedFd(fd) => fd.as_raw_fd() as usize,
       Ident::Pid(pid) => pid as usize,
       Ident::Signal(sig) => sig as usize,
       Ident::Timer(timer, _) => timer,
+      Ident::Wakeup => crate::notifier::WAKEUP_IDENT,
     }
   }
 }
@@ -49,9 +66,11 @@ impl Ident {
     match *self {
       Ident::Filename(_, fd, _) => fd as usize,
       Ident::Fd(fd) => fd as usize,
+      Ident::OwnedFd(ref fd) => fd.as_raw_fd() as usize,
       Ident::Pid(pid) => pid as usize,
       Ident::Signal(sig) => sig as usize,
       Ident::Timer(timer, _) => timer as usize,
+      Ident::Wakeup => crate::notifier::WAKEUP_IDENT,
     }
   }
 }
@@ -1,10 +1,10 @@
 use kqueue_sys::{kevent, kqueue};
 use libc::uintptr_t;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::{Error, Result};
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
 use std::path::Path;
 use std::ptr;
 use std::time::Duration;
@@ -13,11 +13,13 @@ pub use kqueue_sys::constants::*;
 
 mod event;
 mod ident;
+mod notifier;
 mod os;
 mod watched;
 pub use event::Event;
 pub use ident::Ident;
-pub use watched::Watched;
+pub use notifier::Notifier;
+pub use watched::{TriggerMode, Watched};
 
 mod time;
 use crate::time::duration_to_timespec;
@@ -32,23 +34,39 @@ use crate::time::duration_to_timespec;
 /// on the `Watcher`s destruction. If the destructor cannot run for whatever
 /// reason, the underlying kernel object will be leaked.
 ///
-/// Files and file descriptors given to the `Watcher` are presumed to be owned
-/// by the `Watcher`, and will be closed when they're removed from the `Watcher`
-/// or on `Drop`. In a future version, the API will make this explicit via
-/// `OwnedFd`s
+/// Ownership of fds is explicit: `add_fd`/`add_file` borrow and never close
+/// what you give them, while `add_owned_fd` takes an `OwnedFd` and closes it
+/// exactly once, either when it's removed from the `Watcher` or on `Drop`.
+/// `add_filename` opens the file itself, so the `Watcher` always owns (and
+/// closes) those.
 #[derive(Debug)]
 pub struct Watcher {
   watched: HashSet<Watched>,
   queue: RawFd,
   started: bool,
   opts: KqueueOpts,
+
+  /// The raw ident reserved for the `Notifier`'s wakeup event, if one has
+  /// been created. `notifier::WAKEUP_IDENT` (an `EVFILT_USER` event) on
+  /// FreeBSD and DragonFly; the self-pipe's read fd on every other platform.
+  pub(crate) wakeup_ident: Option<uintptr_t>,
+
+  /// The `Notifier` returned by a prior call to `Watcher::notifier()`, kept
+  /// around so later calls can hand back a clone instead of registering a
+  /// second, orphaned wakeup event.
+  notifier: Option<Notifier>,
 }
 
 /// Vnode events
 ///
 /// These are OS-specific, and may not all be supported on your platform. Check
 /// `kqueue(2)` for more information.
-#[derive(Debug)]
+///
+/// A single `EVFILT_VNODE` event can report more than one of these at once
+/// (e.g. `NOTE_WRITE | NOTE_EXTEND`), so `EventData::Vnode` carries a
+/// `Vec<Vnode>` rather than just one; use `EventData::contains` to check for
+/// a specific one.
+#[derive(Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Vnode {
   /// The file was deleted
@@ -89,7 +107,12 @@ pub enum Vnode {
 ///
 /// These are OS-specific, and may not all be supported on your platform. Check
 /// `kqueue(2)` for more information.
-#[derive(Debug)]
+///
+/// A single `EVFILT_PROC` event can report more than one of these at once
+/// (e.g. `NOTE_EXIT | NOTE_FORK`), so `EventData::Proc` carries a
+/// `Vec<Proc>` rather than just one; use `EventData::contains` to check for
+/// a specific one.
+#[derive(Debug, PartialEq, Eq)]
 pub enum Proc {
   /// The watched process exited with the returned exit code
   Exit(usize),
@@ -117,11 +140,13 @@ pub enum Proc {
 /// details on your target OS.
 #[derive(Debug)]
 pub enum EventData {
-  /// Data relating to `Vnode` events
-  Vnode(Vnode),
+  /// Data relating to `Vnode` events. May contain more than one `Vnode` if
+  /// several fflags matched at once.
+  Vnode(Vec<Vnode>),
 
-  /// Data relating to process events
-  Proc(Proc),
+  /// Data relating to process events. May contain more than one `Proc` if
+  /// several fflags matched at once.
+  Proc(Vec<Proc>),
 
   /// The returned number of bytes are ready for reading from the watched
   /// descriptor
@@ -141,10 +166,44 @@ pub enum EventData {
 
   /// Some error was received
   Error(Error),
+
+  /// A `Notifier` obtained from `Watcher::notifier()` was triggered,
+  /// interrupting a blocking `poll_forever`.
+  Wakeup,
+}
+
+/// Lets `EventData::contains` accept either a `Vnode` or a `Proc`.
+pub trait EventDataVariant {
+  fn matches(data: &EventData, item: &Self) -> bool;
+}
+
+impl EventDataVariant for Vnode {
+  fn matches(data: &EventData, item: &Self) -> bool {
+    matches!(data, EventData::Vnode(flags) if flags.contains(item))
+  }
+}
+
+impl EventDataVariant for Proc {
+  fn matches(data: &EventData, item: &Self) -> bool {
+    matches!(data, EventData::Proc(flags) if flags.contains(item))
+  }
 }
 
+impl EventData {
+  /// Checks whether this event carries the given `Vnode` or `Proc`, e.g.
+  /// `event.data.contains(Vnode::Write)`. Returns `false` if `self` isn't
+  /// the matching `EventData` variant.
+  pub fn contains<T: EventDataVariant>(&self, item: T) -> bool {
+    T::matches(self, &item)
+  }
+}
+
+/// The number of events `EventIter` requests per batched `kevent(2)` call.
+const DEFAULT_BATCH_SIZE: usize = 100;
+
 pub struct EventIter<'a> {
-  watcher: &'a Watcher,
+  watcher: &'a mut Watcher,
+  buffer: VecDeque<Event>,
 }
 
 /// Options for a `Watcher`
@@ -179,23 +238,74 @@ impl Watcher {
         queue,
         started: false,
         opts: Default::default(),
+        wakeup_ident: None,
+        notifier: None,
       })
     }
   }
 
-  /// Disables the `clear` flag on a `Watcher`. New events will no longer
-  /// be added with the `EV_CLEAR` flag on `watch`.
+  /// Disables the `clear` flag on a `Watcher`. Subsequent calls to the
+  /// plain `add_*` methods (as opposed to their `add_*_with_mode`
+  /// counterparts) will register with `TriggerMode::Level` instead of
+  /// `TriggerMode::Edge`.
   pub fn disable_clears(&mut self) -> &mut Self {
     self.opts.clear = false;
     self
   }
 
+  /// The `TriggerMode` the plain `add_*` methods use, based on `opts.clear`.
+  fn default_mode(&self) -> TriggerMode {
+    if self.opts.clear {
+      TriggerMode::Edge
+    } else {
+      TriggerMode::Level
+    }
+  }
+
+  /// Returns a thread-safe `Notifier` that can interrupt a blocking
+  /// `poll_forever` on this `Watcher`.
+  ///
+  /// Registers a reserved `EVFILT_USER` event (or, on platforms lacking it,
+  /// a self-pipe) the first time this is called; subsequent calls reuse the
+  /// same registration.
+  pub fn notifier(&mut self) -> Result<Notifier> {
+    if let Some(notifier) = &self.notifier {
+      return Ok(notifier.clone());
+    }
+
+    let (notifier, ident) = Notifier::register(self.queue)?;
+    self.wakeup_ident = Some(ident);
+    self.notifier = Some(notifier.clone());
+    Ok(notifier)
+  }
+
   /// Adds a `pid` to the `Watcher` to be watched
+  ///
+  /// `pid` may not equal `notifier::WAKEUP_IDENT`; it is reserved for the
+  /// `Watcher`'s internal wakeup event and is rejected.
   pub fn add_pid(&mut self, pid: libc::pid_t, filter: EventFilter, flags: FilterFlag) {
+    let mode = self.default_mode();
+    self.add_pid_with_mode(pid, filter, flags, mode)
+  }
+
+  /// Like `add_pid`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_pid_with_mode(
+    &mut self,
+    pid: libc::pid_t,
+    filter: EventFilter,
+    flags: FilterFlag,
+    mode: TriggerMode,
+  ) {
+    if pid as uintptr_t == notifier::WAKEUP_IDENT {
+      return;
+    }
+
     let watch = Watched {
       filter,
       flags,
       ident: Ident::Pid(pid),
+      mode,
     };
 
     if !self.watched.contains(&watch) {
@@ -217,6 +327,19 @@ impl Watcher {
     filename: P,
     filter: EventFilter,
     flags: FilterFlag,
+  ) -> Result<()> {
+    let mode = self.default_mode();
+    self.add_filename_with_mode(filename, filter, flags, mode)
+  }
+
+  /// Like `add_filename`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_filename_with_mode<P: AsRef<Path>>(
+    &mut self,
+    filename: P,
+    filter: EventFilter,
+    flags: FilterFlag,
+    mode: TriggerMode,
   ) -> Result<()> {
     let file = File::open(filename.as_ref())?;
     let fd = file.as_raw_fd();
@@ -224,6 +347,7 @@ impl Watcher {
       filter,
       flags,
       ident: Ident::Filename(file, fd, filename.as_ref().to_string_lossy().into_owned()),
+      mode,
     };
 
     if !self.watched.contains(&watch) {
@@ -233,11 +357,72 @@ impl Watcher {
     Ok(())
   }
 
-  pub fn add_timer(&mut self, id: usize, dur: Duration) {
+  /// Adds a timer to the `Watcher`
+  ///
+  /// Picks the coarsest unit that represents `dur` exactly (seconds,
+  /// microseconds, or nanoseconds, on platforms that support
+  /// `EVFILT_TIMER`'s `NOTE_SECONDS`/`NOTE_USECONDS`/`NOTE_NSECONDS`
+  /// fflags), so sub-millisecond precision is no longer silently dropped.
+  /// On platforms without those fflags, falls back to milliseconds and
+  /// returns an error instead of silently truncating `dur`.
+  ///
+  /// `id` may not equal `notifier::WAKEUP_IDENT`; it is reserved for the
+  /// `Watcher`'s internal wakeup event and is rejected.
+  pub fn add_timer(&mut self, id: usize, dur: Duration) -> Result<()> {
+    let mode = self.default_mode();
+    self.add_timer_with_mode(id, dur, mode)
+  }
+
+  /// Like `add_timer`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_timer_with_mode(&mut self, id: usize, dur: Duration, mode: TriggerMode) -> Result<()> {
+    if id as uintptr_t == notifier::WAKEUP_IDENT {
+      return Err(Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "timer id is reserved for the wakeup event",
+      ));
+    }
+
     let watch = Watched {
       filter: EventFilter::EVFILT_TIMER,
-      flags: FilterFlag::NOTE_FFNOP,
+      flags: timer_unit_flag(dur)?,
       ident: Ident::Timer(id, dur),
+      mode,
+    };
+
+    if !self.watched.contains(&watch) {
+      self.watched.insert(watch);
+    }
+
+    Ok(())
+  }
+
+  /// Adds a signal to the `Watcher` to be watched
+  ///
+  /// `kqueue(2)` signal events are edge-counted and coexist with the
+  /// process's normal signal disposition, so the signal will still be
+  /// delivered (and, by default, handled or terminate the process) unless
+  /// you also `signal(2)`/`sigaction(2)` it to `SIG_IGN` yourself.
+  ///
+  /// `signal` may not equal `notifier::WAKEUP_IDENT`; it is reserved for
+  /// the `Watcher`'s internal wakeup event and is rejected.
+  pub fn add_signal(&mut self, signal: i32, flags: FilterFlag) {
+    let mode = self.default_mode();
+    self.add_signal_with_mode(signal, flags, mode)
+  }
+
+  /// Like `add_signal`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_signal_with_mode(&mut self, signal: i32, flags: FilterFlag, mode: TriggerMode) {
+    if signal as uintptr_t == notifier::WAKEUP_IDENT {
+      return;
+    }
+
+    let watch = Watched {
+      filter: EventFilter::EVFILT_SIGNAL,
+      flags,
+      ident: Ident::Signal(signal),
+      mode,
     };
 
     if !self.watched.contains(&watch) {
@@ -248,12 +433,80 @@ impl Watcher {
   /// Adds a descriptor to a `Watcher`. This or `add_file` is the preferred
   /// way to watch a file
   ///
+  /// `fd` is borrowed, not owned: the `Watcher` will never close it. Use
+  /// `add_owned_fd` if you want the `Watcher` to take ownership and close
+  /// it for you.
+  ///
+  /// `fd` may not equal `notifier::WAKEUP_IDENT`; it is reserved for the
+  /// `Watcher`'s internal wakeup event and is rejected.
+  ///
   /// TODO: Adding new files requires calling `Watcher.watch` again
-  pub fn add_fd(&mut self, fd: RawFd, filter: EventFilter, flags: FilterFlag) {
+  pub fn add_fd(&mut self, fd: BorrowedFd<'_>, filter: EventFilter, flags: FilterFlag) {
+    let mode = self.default_mode();
+    self.add_fd_with_mode(fd, filter, flags, mode)
+  }
+
+  /// Like `add_fd`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_fd_with_mode(
+    &mut self,
+    fd: BorrowedFd<'_>,
+    filter: EventFilter,
+    flags: FilterFlag,
+    mode: TriggerMode,
+  ) {
+    let fd = fd.as_raw_fd();
+
+    if fd as uintptr_t == notifier::WAKEUP_IDENT {
+      return;
+    }
+
     let watch = Watched {
       filter,
       flags,
       ident: Ident::Fd(fd),
+      mode,
+    };
+
+    if !self.watched.contains(&watch) {
+      self.watched.insert(watch);
+    }
+  }
+
+  /// Adds an owned descriptor to a `Watcher`
+  ///
+  /// Unlike `add_fd`, the `Watcher` takes ownership of `fd` and closes it
+  /// exactly once, either when it's removed with `remove_owned_fd` or when
+  /// the `Watcher` is dropped.
+  ///
+  /// `fd` may not equal `notifier::WAKEUP_IDENT`; it is reserved for the
+  /// `Watcher`'s internal wakeup event and is rejected (and closed, since
+  /// we took ownership of it).
+  ///
+  /// TODO: Adding new files requires calling `Watcher.watch` again
+  pub fn add_owned_fd(&mut self, fd: OwnedFd, filter: EventFilter, flags: FilterFlag) {
+    let mode = self.default_mode();
+    self.add_owned_fd_with_mode(fd, filter, flags, mode)
+  }
+
+  /// Like `add_owned_fd`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_owned_fd_with_mode(
+    &mut self,
+    fd: OwnedFd,
+    filter: EventFilter,
+    flags: FilterFlag,
+    mode: TriggerMode,
+  ) {
+    if fd.as_raw_fd() as uintptr_t == notifier::WAKEUP_IDENT {
+      return;
+    }
+
+    let watch = Watched {
+      filter,
+      flags,
+      ident: Ident::OwnedFd(fd),
+      mode,
     };
 
     if !self.watched.contains(&watch) {
@@ -264,9 +517,23 @@ impl Watcher {
   /// Adds a `File` to a `Watcher`. This, or `add_fd` is the preferred way
   /// to watch a file
   ///
+  /// `file` is borrowed, not owned: the `Watcher` will never close it.
+  ///
   /// TODO: Adding new files requires calling `Watcher.watch` again
   pub fn add_file(&mut self, file: &File, filter: EventFilter, flags: FilterFlag) {
-    self.add_fd(file.as_raw_fd(), filter, flags)
+    self.add_fd(file.as_fd(), filter, flags)
+  }
+
+  /// Like `add_file`, but with an explicit `TriggerMode` instead of the
+  /// `Watcher`'s default.
+  pub fn add_file_with_mode(
+    &mut self,
+    file: &File,
+    filter: EventFilter,
+    flags: FilterFlag,
+    mode: TriggerMode,
+  ) {
+    self.add_fd_with_mode(file.as_fd(), filter, flags, mode)
   }
 
   fn delete_kevents(&self, ident: Ident, filter: EventFilter) -> Result<()> {
@@ -311,14 +578,32 @@ impl Watcher {
     }
   }
 
-  /// Removes an fd from a `Watcher`. This closes the fd.
-  pub fn remove_fd(&mut self, fd: RawFd, filter: EventFilter) -> Result<bool> {
+  /// Removes a signal from a `Watcher`
+  pub fn remove_signal(&mut self, signal: i32, filter: EventFilter) -> Result<bool> {
     if self.watched.is_empty() {
       return Ok(false);
     }
 
     let prev_len = self.watched.len();
 
+    self.watched.retain(|w| w.ident != Ident::Signal(signal));
+
+    match self.delete_kevents(Ident::Signal(signal), filter) {
+      Ok(_) => Ok(self.watched.len() != prev_len),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Removes a borrowed fd from a `Watcher`. The fd is not closed, since
+  /// the `Watcher` never owned it; see `add_fd`.
+  pub fn remove_fd(&mut self, fd: BorrowedFd<'_>, filter: EventFilter) -> Result<bool> {
+    if self.watched.is_empty() {
+      return Ok(false);
+    }
+
+    let fd = fd.as_raw_fd();
+    let prev_len = self.watched.len();
+
     self.watched.retain(|w| w.ident != Ident::Fd(fd));
 
     match self.delete_kevents(Ident::Fd(fd), filter) {
@@ -327,9 +612,35 @@ impl Watcher {
     }
   }
 
-  /// Removes a `File` from a `Watcher`
+  /// Removes an owned fd added with `add_owned_fd`. Dropping the matching
+  /// entry closes it exactly once.
+  ///
+  /// Takes a plain `RawFd` rather than a `BorrowedFd`: `add_owned_fd` took
+  /// the caller's `OwnedFd` by value, so there's nothing left for them to
+  /// borrow from afterwards. Stash the raw fd number before handing
+  /// ownership over if you'll need to remove it later.
+  pub fn remove_owned_fd(&mut self, fd: RawFd, filter: EventFilter) -> Result<bool> {
+    if self.watched.is_empty() {
+      return Ok(false);
+    }
+
+    let fd = fd as usize;
+    let prev_len = self.watched.len();
+
+    self
+      .watched
+      .retain(|w| !(matches!(w.ident, Ident::OwnedFd(_)) && w.ident.as_usize() == fd));
+
+    match self.delete_kevents(Ident::Fd(fd as RawFd), filter) {
+      Ok(_) => Ok(prev_len != self.watched.len()),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Removes a `File` from a `Watcher`. The file is not closed, since the
+  /// `Watcher` never owned it; see `add_file`.
   pub fn remove_file(&mut self, file: &File, filter: EventFilter) -> Result<bool> {
-    self.remove_fd(file.as_raw_fd(), filter)
+    self.remove_fd(file.as_fd(), filter)
   }
 
   /// Starts watching for events from `kqueue(2)`. This function needs to
@@ -342,26 +653,46 @@ impl Watcher {
       .map(|watched| {
         let (raw_ident, data) = match watched.ident {
           Ident::Fd(fd) => (fd as uintptr_t, 0),
+          Ident::OwnedFd(ref fd) => (fd.as_raw_fd() as uintptr_t, 0),
           Ident::Filename(_, fd, _) => (fd as uintptr_t, 0),
           Ident::Pid(pid) => (pid as uintptr_t, 0),
           Ident::Signal(sig) => (sig as uintptr_t, 0),
+          // The unit is whichever `NOTE_*` fflag `timer_unit_flag` chose in
+          // `add_timer`; `watched.flags` carries that choice through to here.
+          #[cfg(any(target_os = "freebsd", target_vendor = "apple"))]
+          Ident::Timer(ident, dur) => (
+            ident as uintptr_t,
+            if watched.flags.contains(FilterFlag::NOTE_SECONDS) {
+              dur.as_secs() as i64
+            } else if watched.flags.contains(FilterFlag::NOTE_USECONDS) {
+              dur.as_micros() as i64
+            } else {
+              dur.as_nanos() as i64
+            },
+          ),
+          #[cfg(not(any(target_os = "freebsd", target_vendor = "apple")))]
           Ident::Timer(ident, dur) => (
             ident as uintptr_t,
             (dur.as_secs() * 1000 + (dur.subsec_nanos() / 1_000_000) as u64) as i64,
           ),
+          // The wakeup ident is registered directly against the queue by
+          // `Watcher::notifier()`, not added here.
+          Ident::Wakeup => unreachable!("Ident::Wakeup is never inserted into `watched`"),
         };
 
-        kevent::new(
-          raw_ident,
-          watched.filter,
-          if self.opts.clear {
-            EventFlag::EV_ADD | EventFlag::EV_CLEAR
-          } else {
-            EventFlag::EV_ADD
-          },
-          watched.flags,
-          data,
-        )
+        let event_flags = match watched.mode {
+          TriggerMode::Level => EventFlag::EV_ADD,
+          TriggerMode::Edge => EventFlag::EV_ADD | EventFlag::EV_CLEAR,
+          TriggerMode::Oneshot => EventFlag::EV_ADD | EventFlag::EV_ONESHOT,
+          // `kqueue-sys`'s `EventFlag` has no `EV_DISPATCH` on OpenBSD; fall
+          // back to plain `EV_ADD` there. See `TriggerMode::Dispatch`.
+          #[cfg(not(target_os = "openbsd"))]
+          TriggerMode::Dispatch => EventFlag::EV_ADD | EventFlag::EV_DISPATCH,
+          #[cfg(target_os = "openbsd")]
+          TriggerMode::Dispatch => EventFlag::EV_ADD,
+        };
+
+        kevent::new(raw_ident, watched.filter, event_flags, watched.flags, data)
       })
       .collect();
 
@@ -387,7 +718,7 @@ impl Watcher {
 
   /// Polls for a new event, with an optional timeout. If no `timeout`
   /// is passed, then it will return immediately.
-  pub fn poll(&self, timeout: Option<Duration>) -> Option<Event> {
+  pub fn poll(&mut self, timeout: Option<Duration>) -> Option<Event> {
     // poll will not block indefinitely
     // None -> return immediately
     match timeout {
@@ -398,7 +729,7 @@ impl Watcher {
 
   /// Polls for a new event, with an optional timeout. If no `timeout`
   /// is passed, then it will block until an event is received.
-  pub fn poll_forever(&self, timeout: Option<Duration>) -> Option<Event> {
+  pub fn poll_forever(&mut self, timeout: Option<Duration>) -> Option<Event> {
     if timeout.is_some() {
       self.poll(timeout)
     } else {
@@ -408,8 +739,88 @@ impl Watcher {
 
   /// Creates an iterator that iterates over the queue. This iterator will block
   /// until a new event is received.
-  pub fn iter(&self) -> EventIter<'_> {
-    EventIter { watcher: self }
+  ///
+  /// Internally, events are fetched in batches of `DEFAULT_BATCH_SIZE` via
+  /// `poll_batch` to amortize the cost of `kevent(2)`.
+  pub fn iter(&mut self) -> EventIter<'_> {
+    EventIter {
+      watcher: self,
+      buffer: VecDeque::new(),
+    }
+  }
+
+  /// Drops the bookkeeping for any `TriggerMode::Oneshot` watch that `kev`
+  /// matches, mirroring the kernel's own automatic `EV_ONESHOT` removal.
+  fn forget_if_oneshot(&mut self, kev: &kevent) {
+    self.watched.retain(|w| {
+      !(w.mode == TriggerMode::Oneshot
+        && w.filter == kev.filter
+        && w.ident.as_usize() == kev.ident as usize)
+    });
+  }
+
+  /// Polls for up to `max` events in a single `kevent(2)` call, with an
+  /// optional timeout. If no `timeout` is passed, this blocks until at
+  /// least one event is received.
+  ///
+  /// This is the batched counterpart to `poll`/`poll_forever`: it amortizes
+  /// the `kevent(2)` syscall across many events instead of paying for it
+  /// once per event.
+  pub fn poll_batch(&mut self, max: usize, timeout: Option<Duration>) -> Vec<Event> {
+    if max == 0 {
+      return Vec::new();
+    }
+
+    let mut kevs: Vec<kevent> = (0..max)
+      .map(|_| {
+        kevent::new(
+          0,
+          EventFilter::EVFILT_SYSCOUNT,
+          EventFlag::empty(),
+          FilterFlag::empty(),
+          0,
+        )
+      })
+      .collect();
+
+    // On NetBSD, this is passed as a usize, not i32
+    #[allow(clippy::useless_conversion)]
+    let nevents = i32::try_from(max).unwrap().try_into().unwrap();
+
+    let ret = if let Some(ts) = timeout {
+      unsafe {
+        kevent(
+          self.queue,
+          ptr::null(),
+          0,
+          kevs.as_mut_ptr(),
+          nevents,
+          &duration_to_timespec(ts),
+        )
+      }
+    } else {
+      unsafe { kevent(self.queue, ptr::null(), 0, kevs.as_mut_ptr(), nevents, ptr::null()) }
+    };
+
+    match ret {
+      -1 => vec![Event::from_error(kevs[0], self)],
+      n if n <= 0 => Vec::new(),
+      n => {
+        kevs.truncate(n as usize);
+
+        // Build every `Event` before dropping any oneshot's bookkeeping:
+        // `Event::new` resolves fd/vnode idents via `self.watched`, so
+        // forgetting a watch before all events are built would make that
+        // lookup fail for events still pending construction.
+        let events: Vec<Event> = kevs.iter().map(|kev| Event::new(*kev, self)).collect();
+
+        for kev in &kevs {
+          self.forget_if_oneshot(kev);
+        }
+
+        events
+      },
+    }
   }
 }
 
@@ -422,17 +833,45 @@ impl AsRawFd for Watcher {
 impl Drop for Watcher {
   fn drop(&mut self) {
     unsafe { libc::close(self.queue) };
-    for watched in &self.watched {
-      match watched.ident {
-        Ident::Fd(fd) => unsafe { libc::close(fd) },
-        Ident::Filename(_, fd, _) => unsafe { libc::close(fd) },
-        _ => continue,
-      };
-    }
+    // `watched`'s own `Drop` closes any fds we actually own (`Filename`'s
+    // `File`, `OwnedFd`) exactly once. `Fd` is borrowed and is intentionally
+    // left open.
   }
 }
 
-fn get_event(watcher: &Watcher, timeout: Option<Duration>) -> Option<Event> {
+/// Picks the coarsest `NOTE_*` fflag for `EVFILT_TIMER` that represents
+/// `dur` exactly, following the unit selection rustix's kqueue bindings use.
+#[cfg(any(target_os = "freebsd", target_vendor = "apple"))]
+fn timer_unit_flag(dur: Duration) -> Result<FilterFlag> {
+  let nanos = dur.subsec_nanos();
+
+  Ok(if nanos == 0 {
+    FilterFlag::NOTE_SECONDS
+  } else if nanos % 1_000 == 0 {
+    FilterFlag::NOTE_USECONDS
+  } else {
+    FilterFlag::NOTE_NSECONDS
+  })
+}
+
+/// Platforms without `NOTE_SECONDS`/`NOTE_USECONDS`/`NOTE_NSECONDS` only
+/// support millisecond-granularity timers. Round-trip `dur` through
+/// milliseconds and error out rather than silently truncating it.
+#[cfg(not(any(target_os = "freebsd", target_vendor = "apple")))]
+fn timer_unit_flag(dur: Duration) -> Result<FilterFlag> {
+  let millis = dur.as_millis();
+
+  if millis > u64::MAX as u128 || Duration::from_millis(millis as u64) != dur {
+    return Err(Error::new(
+      std::io::ErrorKind::InvalidInput,
+      "timer duration cannot be represented in milliseconds without truncation",
+    ));
+  }
+
+  Ok(FilterFlag::NOTE_FFNOP)
+}
+
+fn get_event(watcher: &mut Watcher, timeout: Option<Duration>) -> Option<Event> {
   let mut kev = kevent::new(
     0,
     EventFilter::EVFILT_SYSCOUNT,
@@ -459,20 +898,43 @@ fn get_event(watcher: &Watcher, timeout: Option<Duration>) -> Option<Event> {
   match ret {
     -1 => Some(Event::from_error(kev, watcher)),
     0 => None, // timeout expired
-    _ => Some(Event::new(kev, watcher)),
+    _ => {
+      // Build the `Event` before dropping the oneshot's bookkeeping:
+      // `Event::new` resolves fd/vnode idents via `watcher.watched`, so
+      // forgetting the watch first would make that lookup fail.
+      let event = Event::new(kev, watcher);
+      watcher.forget_if_oneshot(&kev);
+      Some(event)
+    },
   }
 }
 
 impl Iterator for EventIter<'_> {
   type Item = Event;
 
-  // rather than call kevent(2) each time, we can likely optimize and
-  // call it once for like 100 items
   fn next(&mut self) -> Option<Self::Item> {
     if !self.watcher.started {
       return None;
     }
 
-    get_event(self.watcher, None)
+    loop {
+      if self.buffer.is_empty() {
+        let batch = self.watcher.poll_batch(DEFAULT_BATCH_SIZE, None);
+
+        if batch.is_empty() {
+          return None;
+        }
+
+        self.buffer.extend(batch);
+      }
+
+      let event = self.buffer.pop_front()?;
+
+      // The wakeup notifier is internal bookkeeping, not a user-facing
+      // event; `poll`/`poll_forever` still surface it directly.
+      if !matches!(event.data, EventData::Wakeup) {
+        return Some(event);
+      }
+    }
   }
 }
@@ -0,0 +1,186 @@
+use std::io::{Error, Result};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::ptr;
+use std::sync::Arc;
+
+use kqueue_sys::{EventFilter, EventFlag, FilterFlag, kevent};
+use libc::uintptr_t;
+
+/// The `ident` reserved for the `Watcher`'s internal wakeup event.
+///
+/// This value can never be returned by the OS for a real fd, pid, signal, or
+/// timer id, so it is used as a sentinel to identify the notifier's own
+/// `kevent`. `add_fd`, `add_owned_fd`, `add_pid`, `add_signal`, and
+/// `add_timer` all reject it to keep a user-supplied ident from colliding
+/// with it.
+///
+/// Only the `EVFILT_USER` registration uses this ident. The self-pipe
+/// fallback identifies its wakeup event by the pipe's read fd instead.
+pub const WAKEUP_IDENT: uintptr_t = uintptr_t::MAX;
+
+/// A thread-safe handle used to interrupt a blocking `poll_forever`.
+///
+/// Obtained from `Watcher::notifier()`. On platforms with `EVFILT_USER`,
+/// `Notifier` only needs the queue's `RawFd` to operate, so it is cheap to
+/// `Clone` and safe to hand to other threads. Calling `notify()` causes the
+/// next (or currently blocked) `kevent(2)` call on the `Watcher` to return
+/// an `Event` whose `data` is `EventData::Wakeup`.
+///
+/// On platforms that fall back to a self-pipe, the pipe's fds are held in
+/// `Arc`s so that every clone shares the same pipe and it's closed exactly
+/// once, when the last `Notifier` (including the one `Watcher` keeps
+/// internally) is dropped.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+  queue: RawFd,
+  #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+  read_fd: Arc<OwnedFd>,
+  #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+  write_fd: Arc<OwnedFd>,
+}
+
+impl Notifier {
+  /// Registers the reserved wakeup ident on `queue` and returns a handle
+  /// that can trigger it, along with the raw ident `Event::new` should
+  /// recognize as the wakeup event.
+  ///
+  /// On platforms without `EVFILT_USER`, this falls back to a self-pipe
+  /// whose read end is registered as an `EVFILT_READ` ident instead.
+  #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+  pub(crate) fn register(queue: RawFd) -> Result<(Notifier, uintptr_t)> {
+    let kev = kevent::new(
+      WAKEUP_IDENT,
+      EventFilter::EVFILT_USER,
+      EventFlag::EV_ADD | EventFlag::EV_CLEAR,
+      FilterFlag::empty(),
+      0,
+    );
+
+    match unsafe {
+      kevent(queue, &kev, 1, ptr::null_mut(), 0, ptr::null())
+    } {
+      -1 => Err(Error::last_os_error()),
+      _ => Ok((Notifier { queue }, WAKEUP_IDENT)),
+    }
+  }
+
+  #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+  pub(crate) fn register(queue: RawFd) -> Result<(Notifier, uintptr_t)> {
+    let mut fds: [RawFd; 2] = [0; 2];
+
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+      return Err(Error::last_os_error());
+    }
+
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+
+    // Neither end blocks: `notify()` must not hang if the pipe buffer ever
+    // fills up, and draining the read end on wakeup must not block once
+    // it's empty.
+    if let Err(err) = set_nonblocking(read_fd).and_then(|_| set_nonblocking(write_fd)) {
+      unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+      }
+      return Err(err);
+    }
+
+    let kev = kevent::new(
+      read_fd as uintptr_t,
+      EventFilter::EVFILT_READ,
+      EventFlag::EV_ADD | EventFlag::EV_CLEAR,
+      FilterFlag::empty(),
+      0,
+    );
+
+    match unsafe {
+      kevent(queue, &kev, 1, ptr::null_mut(), 0, ptr::null())
+    } {
+      -1 => {
+        unsafe {
+          libc::close(read_fd);
+          libc::close(write_fd);
+        }
+        Err(Error::last_os_error())
+      },
+      _ => {
+        // SAFETY: `read_fd`/`write_fd` were just created by `pipe(2)` above
+        // and aren't owned anywhere else yet.
+        let owned_read_fd = unsafe { OwnedFd::from_raw_fd(read_fd) };
+        let owned_write_fd = unsafe { OwnedFd::from_raw_fd(write_fd) };
+
+        Ok((
+          Notifier {
+            queue,
+            read_fd: Arc::new(owned_read_fd),
+            write_fd: Arc::new(owned_write_fd),
+          },
+          read_fd as uintptr_t,
+        ))
+      },
+    }
+  }
+
+  /// Wakes up a thread blocked in `poll` or `poll_forever` on this
+  /// `Watcher`.
+  #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+  pub fn notify(&self) -> Result<()> {
+    let kev = kevent::new(
+      WAKEUP_IDENT,
+      EventFilter::EVFILT_USER,
+      EventFlag::empty(),
+      FilterFlag::NOTE_TRIGGER,
+      0,
+    );
+
+    match unsafe {
+      kevent(self.queue, &kev, 1, ptr::null_mut(), 0, ptr::null())
+    } {
+      -1 => Err(Error::last_os_error()),
+      _ => Ok(()),
+    }
+  }
+
+  /// Wakes up a thread blocked in `poll` or `poll_forever` on this
+  /// `Watcher`.
+  #[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+  pub fn notify(&self) -> Result<()> {
+    match unsafe {
+      libc::write(
+        self.write_fd.as_raw_fd(),
+        [1u8].as_ptr() as *const _,
+        1,
+      )
+    } {
+      -1 => Err(Error::last_os_error()),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Sets `O_NONBLOCK` on `fd`.
+#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+fn set_nonblocking(fd: RawFd) -> Result<()> {
+  let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+
+  if flags == -1 {
+    return Err(Error::last_os_error());
+  }
+
+  match unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } {
+    -1 => Err(Error::last_os_error()),
+    _ => Ok(()),
+  }
+}
+
+/// Drains and discards every byte currently buffered in the self-pipe's
+/// read end. `notify()`'s writes are the only thing that ever fill it, so
+/// this is called each time its wakeup event is delivered; without it, the
+/// pipe buffer fills after enough `notify()` calls and further writes would
+/// block (or, now that the fds are non-blocking, fail with `EWOULDBLOCK`).
+#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+pub(crate) fn drain_self_pipe(read_fd: RawFd) {
+  let mut buf = [0u8; 64];
+
+  while unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) } > 0 {}
+}
@@ -4,11 +4,39 @@ use kqueue_sys::{EventFilter, FilterFlag};
 
 use crate::Ident;
 
+/// Controls how a registered event re-arms once it has fired.
+///
+/// Selected per-watch via the `add_*_with_mode` methods, and mapped to the
+/// corresponding `kevent(2)` flags by `Watcher::watch()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerMode {
+  /// Bare `EV_ADD`: the event keeps firing as long as its condition holds.
+  Level,
+
+  /// `EV_ADD | EV_CLEAR`: the event fires once per state change, then goes
+  /// quiet until the condition changes again.
+  Edge,
+
+  /// `EV_ADD | EV_ONESHOT`: the kernel automatically removes this event
+  /// after it fires once. The `Watcher` mirrors that by dropping its own
+  /// bookkeeping for it as soon as the event is delivered.
+  Oneshot,
+
+  /// `EV_ADD | EV_DISPATCH`: the kernel automatically disables this event
+  /// after it fires once, without removing it. Call `Watcher::watch()`
+  /// again to re-arm it.
+  ///
+  /// OpenBSD's kqueue has no `EV_DISPATCH`; there, this silently behaves
+  /// like `TriggerMode::Level` instead (plain `EV_ADD`, no auto-disable).
+  Dispatch,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Watched {
   pub(crate) filter: EventFilter,
   pub(crate) flags: FilterFlag,
   pub(crate) ident: Ident,
+  pub(crate) mode: TriggerMode,
 }
 
 impl Hash for Watched {